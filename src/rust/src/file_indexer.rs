@@ -1,12 +1,124 @@
-use crate::{FileNode, IndexOptions, IndexResult};
-use ignore::WalkBuilder;
+use crate::{ErrorKind, FileNode, IndexError, IndexOptions, IndexResult};
+use ignore::types::{Types, TypesBuilder};
+use ignore::{Match, WalkBuilder};
 use rayon::prelude::*;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
 use std::time::Instant;
 
-const DANGEROUS_EXTENSIONS: &[&str] = &["exe", "dll", "so", "dylib", "bin", "app"];
-const MAX_DEPTH_DEFAULT: usize = 3;
+pub(crate) const DANGEROUS_EXTENSIONS: &[&str] = &["exe", "dll", "so", "dylib", "bin", "app"];
+pub(crate) const MAX_DEPTH_DEFAULT: usize = 3;
 const MAX_FILE_SIZE_DEFAULT: u64 = 10 * 1024 * 1024; // 10MB
 
+/// Check the dangerous-extension, size-cap, and hidden-file rules shared by
+/// `index_directory` and `watch_directory`. Gitignore-style rules are applied
+/// separately since the two entry points source them differently.
+pub(crate) fn passes_basic_filters(
+    path: &Path,
+    metadata: &std::fs::Metadata,
+    opts: &IndexOptions,
+) -> bool {
+    if let Some(ext) = path.extension() {
+        let ext = ext.to_string_lossy().to_lowercase();
+        if DANGEROUS_EXTENSIONS.contains(&ext.as_str()) {
+            return false;
+        }
+    }
+
+    if !metadata.is_dir() {
+        if let Some(max_size) = opts.max_file_size {
+            if metadata.len() > max_size {
+                return false;
+            }
+        }
+    }
+
+    if let Some(name) = path.file_name() {
+        let name_str = name.to_string_lossy();
+        if name_str.starts_with('.') && name_str != ".git" {
+            // Allow .git for version control
+            return false;
+        }
+    }
+
+    true
+}
+
+/// File type derived from the raw extension (used as a fallback wherever no
+/// `Types` definition name was matched, including when no matcher is configured)
+pub(crate) fn file_type_of(path: &Path) -> Option<String> {
+    path.extension().map(|e| e.to_string_lossy().to_string())
+}
+
+/// Build a `FileNode` for a path that is known to exist on disk
+pub(crate) fn to_file_node(path: &Path, metadata: &std::fs::Metadata) -> FileNode {
+    to_file_node_with_type(path, metadata, None)
+}
+
+/// Build a `FileNode`, preferring a matched `Types` definition name (e.g. "rust")
+/// over the bare extension for `file_type` when one is given
+pub(crate) fn to_file_node_with_type(
+    path: &Path,
+    metadata: &std::fs::Metadata,
+    file_type: Option<String>,
+) -> FileNode {
+    FileNode {
+        name: path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        path: path.to_string_lossy().into_owned(),
+        is_dir: metadata.is_dir(),
+        size: metadata.len(),
+        modified: metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs()),
+        file_type: file_type.or_else(|| file_type_of(path)),
+    }
+}
+
+/// Build a `Types` matcher from `IndexOptions`, registering `ignore`'s default
+/// type definitions (the same ones ripgrep ships with `--type`) plus any
+/// caller-supplied `custom_types`. Returns `None` when no type filtering was
+/// requested, so callers can skip matching entirely in the common case.
+fn build_types_matcher(opts: &IndexOptions) -> Result<Option<Types>, String> {
+    if opts.types.is_none() && opts.types_not.is_none() && opts.custom_types.is_none() {
+        return Ok(None);
+    }
+
+    let mut builder = TypesBuilder::new();
+    builder.add_defaults();
+
+    if let Some(custom_types) = &opts.custom_types {
+        for def in custom_types {
+            for glob in &def.globs {
+                builder
+                    .add(&def.name, glob)
+                    .map_err(|e| format!("Invalid type definition \"{}\": {}", def.name, e))?;
+            }
+        }
+    }
+
+    if let Some(types) = &opts.types {
+        for name in types {
+            builder.select(name);
+        }
+    }
+
+    if let Some(types_not) = &opts.types_not {
+        for name in types_not {
+            builder.negate(name);
+        }
+    }
+
+    builder
+        .build()
+        .map(Some)
+        .map_err(|e| format!("Failed to build type matcher: {}", e))
+}
+
 /// Index a directory with high performance
 ///
 /// # Arguments
@@ -28,7 +140,123 @@ pub fn index_directory(
     let start = Instant::now();
     let opts = options.unwrap_or_default();
 
-    // Validate path exists
+    let errors = Arc::new(Mutex::new(Vec::new()));
+    let files = walk_one(path, &opts, &errors)?;
+
+    let duration = start.elapsed();
+    let total_size = files.iter().map(|f| f.size).sum();
+    let total_count = files.len();
+
+    Ok(IndexResult {
+        files,
+        total_count,
+        total_size,
+        duration_ms: duration.as_millis() as u64,
+        errors: drain_errors(errors),
+    })
+}
+
+/// Index several directory roots in parallel and merge them into a single
+/// `IndexResult`, deduplicating entries that resolve to the same canonical
+/// path (important when roots overlap or are nested).
+///
+/// # Arguments
+/// * `paths` - The directory roots to index
+/// * `options` - Indexing options (uses defaults if None), applied uniformly to every root
+///
+/// # Returns
+/// * `Result<IndexResult>` - Merged indexing result with files, stats, and timing.
+///   Roots that don't exist or aren't directories are skipped and reported
+///   alongside per-entry walk failures in `errors`, rather than failing the
+///   whole call.
+pub fn index_directories(
+    paths: Vec<String>,
+    options: Option<IndexOptions>,
+) -> Result<IndexResult, String> {
+    let start = Instant::now();
+    let opts = options.unwrap_or_default();
+    let errors = Arc::new(Mutex::new(Vec::new()));
+
+    let per_root: Vec<(String, Result<Vec<FileNode>, String>)> = paths
+        .par_iter()
+        .map(|path| (path.clone(), walk_one(path, &opts, &errors)))
+        .collect();
+
+    // Canonicalizing every passing file is wasteful when roots don't overlap,
+    // since entries from distinct, non-nested roots can never collide — so
+    // only pay for per-entry canonicalization when two roots actually could.
+    let canonical_roots: Vec<std::path::PathBuf> = paths
+        .iter()
+        .map(|p| std::fs::canonicalize(p).unwrap_or_else(|_| std::path::PathBuf::from(p)))
+        .collect();
+    let roots_may_overlap = canonical_roots.iter().enumerate().any(|(i, a)| {
+        canonical_roots
+            .iter()
+            .enumerate()
+            .any(|(j, b)| i != j && b.starts_with(a))
+    });
+
+    let mut seen: std::collections::HashSet<std::path::PathBuf> = std::collections::HashSet::new();
+    let mut files: Vec<FileNode> = Vec::new();
+
+    for (path, result) in per_root {
+        match result {
+            Ok(root_files) => {
+                for node in root_files {
+                    if roots_may_overlap {
+                        let canonical = std::fs::canonicalize(&node.path)
+                            .unwrap_or_else(|_| std::path::PathBuf::from(&node.path));
+                        if seen.insert(canonical) {
+                            files.push(node);
+                        }
+                    } else {
+                        files.push(node);
+                    }
+                }
+            }
+            Err(message) => record_root_error(path, message, &errors),
+        }
+    }
+
+    // `build_parallel`'s entries arrive in work-stealing order, which isn't
+    // reproducible run-to-run; sort by path so the merged result is stable.
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let duration = start.elapsed();
+    let total_size = files.iter().map(|f| f.size).sum();
+    let total_count = files.len();
+
+    Ok(IndexResult {
+        files,
+        total_count,
+        total_size,
+        duration_ms: duration.as_millis() as u64,
+        errors: drain_errors(errors),
+    })
+}
+
+/// Walk a single root, applying gitignore/.dmignore/hidden/dangerous-extension/
+/// size filters, and return the passing entries. Unreadable entries (permission
+/// denied, vanished metadata, etc.) are pushed onto `errors` rather than dropped.
+/// Shared by `index_directory` and `index_directories`.
+fn walk_one(
+    path: &str,
+    opts: &IndexOptions,
+    errors: &Arc<Mutex<Vec<IndexError>>>,
+) -> Result<Vec<FileNode>, String> {
+    Ok(walk_one_with_inodes(path, opts, errors)?
+        .into_iter()
+        .map(|(node, _inode)| node)
+        .collect())
+}
+
+/// Like `walk_one`, but also returns each entry's `(dev, ino)` where available
+/// so callers (namely `index_tree`'s hardlink dedup) don't pay for a second walk.
+pub(crate) fn walk_one_with_inodes(
+    path: &str,
+    opts: &IndexOptions,
+    errors: &Arc<Mutex<Vec<IndexError>>>,
+) -> Result<Vec<(FileNode, Option<(u64, u64)>)>, String> {
     let path_obj = std::path::Path::new(path);
     if !path_obj.exists() {
         return Err(format!("Path does not exist: {}", path));
@@ -38,7 +266,6 @@ pub fn index_directory(
         return Err(format!("Path is not a directory: {}", path));
     }
 
-    // Build walker with options
     let mut walker = WalkBuilder::new(path);
 
     if opts.respect_gitignore {
@@ -53,76 +280,176 @@ pub fn index_directory(
 
     walker.follow_links(opts.follow_links);
 
-    // Build parallel iterator
-    let files: Vec<FileNode> = walker
+    // Built once up front; re-parsing glob definitions per-entry would be wasteful
+    // since the walk runs across many worker threads.
+    let types_matcher = build_types_matcher(opts)?;
+
+    let files: Vec<(FileNode, Option<(u64, u64)>)> = walker
         .build_parallel()
         .filter_map(|entry| {
-            let entry = entry.ok()?;
-            let metadata = entry.metadata().ok()?;
-
-            let path = entry.path();
-
-            // Skip dangerous binary files
-            if let Some(ext) = path.extension() {
-                let ext = ext.to_string_lossy().to_lowercase();
-                if DANGEROUS_EXTENSIONS.contains(&ext.as_str()) {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(err) => {
+                    record_entry_error(err, errors);
                     return None;
                 }
-            }
-
-            // Filter large files (only for files, not directories)
-            if !metadata.is_dir() {
-                if let Some(max_size) = opts.max_file_size {
-                    if metadata.len() > max_size {
-                        return None;
-                    }
+            };
+            let metadata = match entry.metadata() {
+                Ok(metadata) => metadata,
+                Err(err) => {
+                    record_entry_error(err, errors);
+                    return None;
                 }
+            };
+            let path = entry.path();
+
+            if !passes_basic_filters(path, &metadata, opts) {
+                return None;
             }
 
-            // Skip hidden files/directories (Unix-style)
-            if let Some(name) = path.file_name() {
-                let name_str = name.to_string_lossy();
-                if name_str.starts_with('.') && name_str != ".git" {
-                    // Allow .git for version control
-                    return None;
+            let matched_type = if metadata.is_dir() {
+                None
+            } else if let Some(types) = &types_matcher {
+                match types.matched(path, false) {
+                    Match::Ignore(_) => return None,
+                    Match::Whitelist(def) => Some(def.name().to_string()),
+                    Match::None if opts.types.is_some() => return None,
+                    Match::None => None,
                 }
-            }
+            } else {
+                None
+            };
 
-            // Get file type from extension
-            let file_type = path
-                .extension()
-                .map(|e| e.to_string_lossy().to_string());
-
-            Some(FileNode {
-                name: entry.file_name().to_string_lossy().into(),
-                path: path.to_string_lossy().into(),
-                is_dir: metadata.is_dir(),
-                size: metadata.len(),
-                modified: metadata
-                    .modified()
-                    .ok()
-                    .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-                    .map(|d| d.as_secs()),
-                file_type,
-            })
+            let inode = inode_of(&metadata);
+            Some((to_file_node_with_type(path, &metadata, matched_type), inode))
         })
         .collect();
 
-    let duration = start.elapsed();
-    let total_size = files.iter().map(|f| f.size).sum();
-    let total_count = files.len();
+    Ok(files)
+}
 
-    Ok(IndexResult {
-        files,
-        total_count,
-        total_size,
-        duration_ms: duration.as_millis() as u64,
-    })
+/// `(dev, ino)` for a path, used to detect hardlinks when aggregating a
+/// directory tree. Unavailable (and therefore unused for dedup) off Unix.
+#[cfg(unix)]
+fn inode_of(metadata: &std::fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn inode_of(_metadata: &std::fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+/// Classify an `ignore::Error` into a structured `IndexError` and push it onto
+/// the shared accumulator. Collected through a mutex because the walk runs
+/// under `build_parallel` across worker threads.
+fn record_entry_error(err: ignore::Error, errors: &Arc<Mutex<Vec<IndexError>>>) {
+    let path = err
+        .path()
+        .map(|p| p.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let (kind, message) = match err.io_error() {
+        Some(io_err) => (classify_io_error(io_err.kind()), io_err.to_string()),
+        None => (ErrorKind::Unknown, err.to_string()),
+    };
+
+    errors.lock().unwrap().push(IndexError {
+        path,
+        kind,
+        message,
+    });
+}
+
+/// Classify a failed root (from `walk_one`'s existence/is-dir checks, via
+/// `index_directories`) into a structured `IndexError` and push it onto the
+/// same accumulator per-entry walk failures use, so callers have a single
+/// error channel to read instead of two differently-shaped ones.
+fn record_root_error(path: String, message: String, errors: &Arc<Mutex<Vec<IndexError>>>) {
+    let kind = if message.starts_with("Path does not exist") {
+        ErrorKind::NotFound
+    } else {
+        ErrorKind::Unknown
+    };
+
+    errors.lock().unwrap().push(IndexError {
+        path,
+        kind,
+        message,
+    });
+}
+
+fn classify_io_error(kind: std::io::ErrorKind) -> ErrorKind {
+    match kind {
+        std::io::ErrorKind::NotFound => ErrorKind::NotFound,
+        std::io::ErrorKind::PermissionDenied => ErrorKind::PermissionDenied,
+        _ => ErrorKind::Unknown,
+    }
+}
+
+/// Drain the shared error accumulator into a plain `Vec` once the walk is done
+pub(crate) fn drain_errors(errors: Arc<Mutex<Vec<IndexError>>>) -> Vec<IndexError> {
+    Arc::try_unwrap(errors)
+        .map(|mutex| mutex.into_inner().unwrap())
+        .unwrap_or_else(|arc| arc.lock().unwrap().clone())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn test_index_directories_dedupes_overlapping_roots() {
+        let dir = TempDir::new("index_directories_dedup");
+        std::fs::write(dir.path().join("a.txt"), b"hello").unwrap();
+
+        let root = dir.path().to_string_lossy().into_owned();
+        let result = index_directories(vec![root.clone(), root], None).unwrap();
+
+        assert_eq!(result.total_count, 1);
+        assert!(result.errors.is_empty());
+    }
+
+    #[test]
+    fn test_index_directories_reports_missing_root_as_error() {
+        let dir = TempDir::new("index_directories_missing_root");
+        let missing = dir.path().join("does-not-exist");
+
+        let result = index_directories(vec![missing.to_string_lossy().into_owned()], None).unwrap();
+
+        assert_eq!(result.total_count, 0);
+        assert_eq!(result.errors.len(), 1);
+        assert!(matches!(result.errors[0].kind, ErrorKind::NotFound));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_index_directory_collects_permission_denied_error() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = TempDir::new("permission_denied");
+        let unreadable = dir.path().join("locked");
+        std::fs::create_dir(&unreadable).unwrap();
+        std::fs::write(unreadable.join("secret.txt"), b"shh").unwrap();
+        std::fs::set_permissions(&unreadable, std::fs::Permissions::from_mode(0o000)).unwrap();
+
+        let result = index_directory(&dir.path().to_string_lossy(), None);
+
+        // Restore permissions so `TempDir`'s drop can clean up regardless of outcome
+        std::fs::set_permissions(&unreadable, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let result = result.unwrap();
+        assert!(
+            result
+                .errors
+                .iter()
+                .any(|e| matches!(e.kind, ErrorKind::PermissionDenied)),
+            "expected a permission-denied error, got: {:?}",
+            result.errors
+        );
+    }
 
     #[test]
     fn test_index_options_default() {
@@ -139,4 +466,75 @@ mod tests {
         assert!(DANGEROUS_EXTENSIONS.contains(&"dll"));
         assert!(DANGEROUS_EXTENSIONS.contains(&"so"));
     }
+
+    #[test]
+    fn test_types_filter_includes_only_selected_type() {
+        let dir = TempDir::new("types_filter");
+        std::fs::write(dir.path().join("main.rs"), b"fn main() {}").unwrap();
+        std::fs::write(dir.path().join("notes.md"), b"# notes").unwrap();
+
+        let opts = IndexOptions {
+            types: Some(vec!["rust".to_string()]),
+            ..IndexOptions::default()
+        };
+
+        let result = index_directory(&dir.path().to_string_lossy(), Some(opts)).unwrap();
+        // Type filters apply to files only; the root directory entry is always kept
+        let names: Vec<&str> = result
+            .files
+            .iter()
+            .filter(|f| !f.is_dir)
+            .map(|f| f.name.as_str())
+            .collect();
+
+        assert_eq!(names, vec!["main.rs"]);
+    }
+
+    #[test]
+    fn test_types_not_excludes_matched_type() {
+        let dir = TempDir::new("types_not_filter");
+        std::fs::write(dir.path().join("main.rs"), b"fn main() {}").unwrap();
+        std::fs::write(dir.path().join("notes.md"), b"# notes").unwrap();
+
+        let opts = IndexOptions {
+            types_not: Some(vec!["rust".to_string()]),
+            ..IndexOptions::default()
+        };
+
+        let result = index_directory(&dir.path().to_string_lossy(), Some(opts)).unwrap();
+        let names: Vec<&str> = result
+            .files
+            .iter()
+            .filter(|f| !f.is_dir)
+            .map(|f| f.name.as_str())
+            .collect();
+
+        assert_eq!(names, vec!["notes.md"]);
+    }
+
+    #[test]
+    fn test_custom_types_definition_is_matched() {
+        let dir = TempDir::new("custom_types_filter");
+        std::fs::write(dir.path().join("data.widget"), b"widget data").unwrap();
+        std::fs::write(dir.path().join("notes.md"), b"# notes").unwrap();
+
+        let opts = IndexOptions {
+            types: Some(vec!["widget".to_string()]),
+            custom_types: Some(vec![crate::TypeDefinition {
+                name: "widget".to_string(),
+                globs: vec!["*.widget".to_string()],
+            }]),
+            ..IndexOptions::default()
+        };
+
+        let result = index_directory(&dir.path().to_string_lossy(), Some(opts)).unwrap();
+        let names: Vec<&str> = result
+            .files
+            .iter()
+            .filter(|f| !f.is_dir)
+            .map(|f| f.name.as_str())
+            .collect();
+
+        assert_eq!(names, vec!["data.widget"]);
+    }
 }
@@ -0,0 +1,142 @@
+use crate::file_indexer::{drain_errors, walk_one_with_inodes};
+use crate::{DirNode, FileNode, IndexOptions, TreeResult};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Index a directory into a `du`-style tree with cumulative sizes per directory,
+/// instead of `index_directory`'s flat `files` list plus a single `total_size`.
+///
+/// # Arguments
+/// * `path` - The directory root to index
+/// * `options` - Indexing options (uses defaults if None); `follow_links` also
+///   governs whether symlinked directories are descended into for the aggregate
+///
+/// # Returns
+/// * `Result<TreeResult>` - the root node, with `aggregate_size` summing every
+///   descendant file's bytes and `aggregate_count` counting its own entry plus
+///   every descendant, and any per-entry failures
+///   hit while walking (permission denied, metadata read failures, etc.)
+pub fn index_tree(path: &str, options: Option<IndexOptions>) -> Result<TreeResult, String> {
+    let opts = options.unwrap_or_default();
+    let errors = Arc::new(Mutex::new(Vec::new()));
+
+    // Collect all passing entries in parallel first, same as `index_directory`,
+    // then fold them into a tree bottom-up below.
+    let entries = walk_one_with_inodes(path, &opts, &errors)?;
+
+    let mut nodes: HashMap<PathBuf, FileNode> = HashMap::new();
+    let mut inodes: HashMap<PathBuf, Option<(u64, u64)>> = HashMap::new();
+    let mut children_of: HashMap<PathBuf, Vec<PathBuf>> = HashMap::new();
+
+    for (node, inode) in entries {
+        let node_path = PathBuf::from(&node.path);
+        if let Some(parent) = node_path.parent() {
+            children_of
+                .entry(parent.to_path_buf())
+                .or_default()
+                .push(node_path.clone());
+        }
+        inodes.insert(node_path.clone(), inode);
+        nodes.insert(node_path, node);
+    }
+
+    let root = PathBuf::from(path);
+    let mut seen_inodes: HashSet<(u64, u64)> = HashSet::new();
+
+    let tree = build_dir_node(&root, &mut nodes, &children_of, &inodes, &mut seen_inodes)
+        .ok_or_else(|| format!("No entries found under root: {}", path))?;
+
+    Ok(TreeResult {
+        root: tree,
+        errors: drain_errors(errors),
+    })
+}
+
+/// Fold entries into a tree by grouping them by parent path (`children_of`) and
+/// recursing depth-first, so each directory's aggregate is computed from its
+/// children's aggregates before it returns to its own parent.
+fn build_dir_node(
+    path: &Path,
+    nodes: &mut HashMap<PathBuf, FileNode>,
+    children_of: &HashMap<PathBuf, Vec<PathBuf>>,
+    inodes: &HashMap<PathBuf, Option<(u64, u64)>>,
+    seen_inodes: &mut HashSet<(u64, u64)>,
+) -> Option<DirNode> {
+    let node = nodes.remove(path)?;
+
+    if !node.is_dir {
+        let counts = match inodes.get(path).copied().flatten() {
+            Some(inode) => seen_inodes.insert(inode),
+            None => true,
+        };
+        let aggregate_size = if counts { node.size } else { 0 };
+        let aggregate_count = if counts { 1 } else { 0 };
+        return Some(DirNode {
+            node,
+            children: Vec::new(),
+            aggregate_size,
+            aggregate_count,
+        });
+    }
+
+    let mut children = Vec::new();
+    let mut aggregate_size = 0u64;
+    // A directory counts as one entry towards its own aggregate (it contributes
+    // no bytes itself), matching `DirNode`'s "itself plus all descendants" contract.
+    let mut aggregate_count = 1u64;
+
+    if let Some(child_paths) = children_of.get(path) {
+        for child_path in child_paths {
+            if let Some(child) = build_dir_node(child_path, nodes, children_of, inodes, seen_inodes)
+            {
+                aggregate_size += child.aggregate_size;
+                aggregate_count += child.aggregate_count;
+                children.push(child);
+            }
+        }
+    }
+
+    Some(DirNode {
+        node,
+        children,
+        aggregate_size,
+        aggregate_count,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn test_index_tree_aggregates_nested_sizes() {
+        let dir = TempDir::new("index_tree_aggregate");
+        std::fs::write(dir.path().join("a.txt"), b"12345").unwrap();
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        std::fs::write(dir.path().join("sub").join("b.txt"), b"1234567890").unwrap();
+
+        let result = index_tree(&dir.path().to_string_lossy(), None).unwrap();
+
+        assert_eq!(result.root.aggregate_size, 15);
+        // root + a.txt + sub/ + sub/b.txt
+        assert_eq!(result.root.aggregate_count, 4);
+        assert!(result.errors.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_index_tree_dedupes_hardlinks() {
+        let dir = TempDir::new("index_tree_hardlink");
+        let original = dir.path().join("a.txt");
+        std::fs::write(&original, b"12345").unwrap();
+        std::fs::hard_link(&original, dir.path().join("b.txt")).unwrap();
+
+        let result = index_tree(&dir.path().to_string_lossy(), None).unwrap();
+
+        // The hardlinked second entry is only counted once: root + a.txt.
+        assert_eq!(result.root.aggregate_size, 5);
+        assert_eq!(result.root.aggregate_count, 2);
+    }
+}
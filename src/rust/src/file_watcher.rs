@@ -0,0 +1,549 @@
+use crate::file_indexer::{file_type_of, passes_basic_filters, to_file_node, MAX_DEPTH_DEFAULT};
+use crate::{FileNode, IndexOptions};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+use napi::threadsafe_function::{ErrorStrategy, ThreadsafeFunction, ThreadsafeFunctionCallMode};
+use napi_derive::napi;
+use notify::event::{ModifyKind, RenameMode};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, RecvTimeoutError};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const DEBOUNCE_WINDOW_MS: u64 = 100;
+
+/// Kind of change reported by `watch_directory`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[napi(string_enum)]
+pub enum ChangeKind {
+    Created,
+    Modified,
+    Removed,
+    Renamed,
+}
+
+/// A single filtered, debounced file-system change
+#[derive(Serialize, Deserialize, Debug)]
+#[napi(object)]
+pub struct FileChangeEvent {
+    pub node: FileNode,
+    pub kind: ChangeKind,
+}
+
+/// Handle returned by `watch_directory`; call `stop()` to unsubscribe
+#[napi]
+pub struct WatchHandle {
+    stopped: Arc<AtomicBool>,
+}
+
+#[napi]
+impl WatchHandle {
+    /// Stop watching and tear down the underlying OS watcher
+    #[napi]
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::SeqCst);
+    }
+}
+
+struct PendingChange {
+    kind: ChangeKind,
+    seen_at: Instant,
+}
+
+/// Pairs split `RenameMode::From`/`RenameMode::To` events (common for
+/// cross-directory moves, and the only form some backends emit) into a single
+/// `Removed` (old path) plus `Renamed` (new path), the same way the combined
+/// `RenameMode::Both` event is handled. Matched by the backend's rename cookie
+/// (`Event::tracker`) where available, falling back to FIFO order against other
+/// pending `From`s. Entries older than `MAX_PAIR_AGE_MS` are flushed as plain removals so a move
+/// out of the watched tree (no matching `To`) doesn't wait forever.
+struct RenameTracker {
+    by_cookie: HashMap<usize, (PathBuf, Instant)>,
+    unmatched_from: std::collections::VecDeque<(PathBuf, Instant)>,
+}
+
+const MAX_PAIR_AGE_MS: u64 = 500;
+
+impl RenameTracker {
+    fn new() -> Self {
+        Self {
+            by_cookie: HashMap::new(),
+            unmatched_from: std::collections::VecDeque::new(),
+        }
+    }
+
+    fn record_from(&mut self, path: PathBuf, cookie: Option<usize>, now: Instant) {
+        match cookie {
+            Some(cookie) => {
+                self.by_cookie.insert(cookie, (path, now));
+            }
+            None => self.unmatched_from.push_back((path, now)),
+        }
+    }
+
+    /// Find and remove the `From` path paired with this `To` event, if any
+    fn take_pair(&mut self, cookie: Option<usize>) -> Option<PathBuf> {
+        if let Some(cookie) = cookie {
+            if let Some((path, _)) = self.by_cookie.remove(&cookie) {
+                return Some(path);
+            }
+        }
+        self.unmatched_from.pop_front().map(|(path, _)| path)
+    }
+
+    /// Drain `From` events that never saw a matching `To` within `MAX_PAIR_AGE_MS`
+    fn take_stale(&mut self, now: Instant) -> Vec<PathBuf> {
+        let max_age = Duration::from_millis(MAX_PAIR_AGE_MS);
+        let mut stale = Vec::new();
+
+        self.by_cookie.retain(|_, (path, seen_at)| {
+            if now.duration_since(*seen_at) >= max_age {
+                stale.push(path.clone());
+                false
+            } else {
+                true
+            }
+        });
+
+        while let Some((path, seen_at)) = self.unmatched_from.front() {
+            if now.duration_since(*seen_at) >= max_age {
+                stale.push(path.clone());
+                self.unmatched_from.pop_front();
+            } else {
+                break;
+            }
+        }
+
+        stale
+    }
+}
+
+/// Per-directory `.gitignore`/`.dmignore` matchers, built lazily and cached by
+/// directory so re-validation on each event doesn't re-parse ignore files.
+/// Mirrors how a real gitignore stack works: a directory's rules apply to
+/// everything under it, so checking a path means checking every ancestor
+/// directory between the watched root and the path's own parent.
+struct IgnoreStack {
+    cache: HashMap<PathBuf, Gitignore>,
+}
+
+impl IgnoreStack {
+    fn new() -> Self {
+        Self {
+            cache: HashMap::new(),
+        }
+    }
+
+    fn matcher_for(&mut self, dir: &Path) -> &Gitignore {
+        self.cache.entry(dir.to_path_buf()).or_insert_with(|| {
+            let mut builder = GitignoreBuilder::new(dir);
+            let _ = builder.add(dir.join(".gitignore"));
+            let _ = builder.add(dir.join(".dmignore"));
+            builder.build().unwrap_or_else(|_| Gitignore::empty())
+        })
+    }
+
+    fn is_ignored(&mut self, root: &Path, path: &Path, is_dir: bool) -> bool {
+        let mut dirs = Vec::new();
+        let mut current = path.parent();
+        while let Some(dir) = current {
+            dirs.push(dir.to_path_buf());
+            if dir == root || !dir.starts_with(root) {
+                break;
+            }
+            current = dir.parent();
+        }
+        dirs.reverse();
+
+        let mut ignored = false;
+        for dir in dirs {
+            match self.matcher_for(&dir).matched_path_or_any_parents(path, is_dir) {
+                Match::Ignore(_) => ignored = true,
+                Match::Whitelist(_) => ignored = false,
+                Match::None => {}
+            }
+        }
+        ignored
+    }
+}
+
+/// Watch a directory and emit batched `FileChangeEvent`s as the tree changes
+///
+/// # Arguments
+/// * `path` - The directory path to watch
+/// * `options` - Indexing options (uses defaults if None); the same gitignore/.dmignore/
+///   hidden/dangerous-extension/size filters `index_directory` applies are applied here
+///   to paths that still exist; removals are reported without any of these filters
+/// * `callback` - Invoked with a batch of events once per debounce window
+///
+/// # Returns
+/// * `Result<WatchHandle>` - call `.stop()` on it to unsubscribe
+pub fn watch_directory(
+    path: &str,
+    options: Option<IndexOptions>,
+    callback: ThreadsafeFunction<Vec<FileChangeEvent>, ErrorStrategy::CalleeHandled>,
+) -> Result<WatchHandle, String> {
+    let opts = options.unwrap_or_default();
+    let root = PathBuf::from(path);
+
+    if !root.is_dir() {
+        return Err(format!("Path is not a directory: {}", path));
+    }
+
+    // Per-directory matchers are built lazily as events touch new directories
+    // and cached for the life of the watch, so this stays a one-time parse per
+    // directory rather than per event.
+    let mut ignore_stack = IgnoreStack::new();
+
+    let max_depth = opts.max_depth.unwrap_or(MAX_DEPTH_DEFAULT);
+
+    let (tx, rx) = channel::<Event>();
+    let mut watcher: RecommendedWatcher =
+        notify::recommended_watcher(move |res: notify::Result<Event>| {
+            if let Ok(event) = res {
+                let _ = tx.send(event);
+            }
+        })
+        .map_err(|e| format!("Failed to start file watcher: {}", e))?;
+
+    watcher
+        .watch(&root, RecursiveMode::Recursive)
+        .map_err(|e| format!("Failed to watch {}: {}", path, e))?;
+
+    let stopped = Arc::new(AtomicBool::new(false));
+    let stopped_for_thread = stopped.clone();
+    let root_for_thread = root.clone();
+
+    std::thread::spawn(move || {
+        // Keep the watcher alive for as long as the debounce loop runs.
+        let _watcher = watcher;
+        let mut pending: HashMap<PathBuf, PendingChange> = HashMap::new();
+        let mut renames = RenameTracker::new();
+        let debounce = Duration::from_millis(DEBOUNCE_WINDOW_MS);
+
+        while !stopped_for_thread.load(Ordering::SeqCst) {
+            match rx.recv_timeout(debounce) {
+                Ok(event) => queue_event(
+                    &event,
+                    &root_for_thread,
+                    max_depth,
+                    &mut pending,
+                    &mut renames,
+                ),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+
+            let now = Instant::now();
+            for orphaned_from in renames.take_stale(now) {
+                if within_depth(&root_for_thread, &orphaned_from, max_depth) {
+                    pending.insert(
+                        orphaned_from,
+                        PendingChange {
+                            kind: ChangeKind::Removed,
+                            seen_at: now,
+                        },
+                    );
+                }
+            }
+
+            let batch = drain_ready(&mut pending, debounce, &root_for_thread, &mut ignore_stack, &opts);
+            if !batch.is_empty() {
+                callback.call(Ok(batch), ThreadsafeFunctionCallMode::NonBlocking);
+            }
+        }
+    });
+
+    Ok(WatchHandle { stopped })
+}
+
+fn queue_event(
+    event: &Event,
+    root: &Path,
+    max_depth: usize,
+    pending: &mut HashMap<PathBuf, PendingChange>,
+    renames: &mut RenameTracker,
+) {
+    let now = Instant::now();
+
+    match event.kind {
+        // A rename with both the old and new path is reported as a single event;
+        // split it into a paired `Removed` (old path) + `Renamed` (new path) so callers
+        // don't need to special-case it.
+        EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if event.paths.len() == 2 => {
+            let from = &event.paths[0];
+            let to = &event.paths[1];
+            if within_depth(root, from, max_depth) {
+                pending.insert(
+                    from.clone(),
+                    PendingChange {
+                        kind: ChangeKind::Removed,
+                        seen_at: now,
+                    },
+                );
+            }
+            if within_depth(root, to, max_depth) {
+                pending.insert(
+                    to.clone(),
+                    PendingChange {
+                        kind: ChangeKind::Renamed,
+                        seen_at: now,
+                    },
+                );
+            }
+            return;
+        }
+        // Some backends (notably Linux inotify for cross-directory moves) report the
+        // old and new paths as two separate events instead, correlated by a cookie.
+        EventKind::Modify(ModifyKind::Name(RenameMode::From)) if event.paths.len() == 1 => {
+            renames.record_from(event.paths[0].clone(), event.attrs.tracker(), now);
+            return;
+        }
+        EventKind::Modify(ModifyKind::Name(RenameMode::To)) if event.paths.len() == 1 => {
+            let to = event.paths[0].clone();
+            if let Some(from) = renames.take_pair(event.attrs.tracker()) {
+                if within_depth(root, &from, max_depth) {
+                    pending.insert(
+                        from,
+                        PendingChange {
+                            kind: ChangeKind::Removed,
+                            seen_at: now,
+                        },
+                    );
+                }
+                if within_depth(root, &to, max_depth) {
+                    pending.insert(
+                        to,
+                        PendingChange {
+                            kind: ChangeKind::Renamed,
+                            seen_at: now,
+                        },
+                    );
+                }
+            } else if within_depth(root, &to, max_depth) {
+                pending.insert(
+                    to,
+                    PendingChange {
+                        kind: ChangeKind::Created,
+                        seen_at: now,
+                    },
+                );
+            }
+            return;
+        }
+        _ => {}
+    }
+
+    let kind = classify(&event.kind);
+    for p in &event.paths {
+        if !within_depth(root, p, max_depth) {
+            continue;
+        }
+        pending.insert(
+            p.clone(),
+            PendingChange {
+                kind: kind.clone(),
+                seen_at: now,
+            },
+        );
+    }
+}
+
+fn drain_ready(
+    pending: &mut HashMap<PathBuf, PendingChange>,
+    debounce: Duration,
+    root: &Path,
+    ignore_stack: &mut IgnoreStack,
+    opts: &IndexOptions,
+) -> Vec<FileChangeEvent> {
+    let ready: Vec<PathBuf> = pending
+        .iter()
+        .filter(|(_, change)| change.seen_at.elapsed() >= debounce)
+        .map(|(p, _)| p.clone())
+        .collect();
+
+    let mut batch = Vec::with_capacity(ready.len());
+    for p in ready {
+        if let Some(change) = pending.remove(&p) {
+            if let Some(event) = build_change_event(&p, change.kind, root, ignore_stack, opts) {
+                batch.push(event);
+            }
+        }
+    }
+    batch
+}
+
+fn classify(kind: &EventKind) -> ChangeKind {
+    match kind {
+        EventKind::Create(_) => ChangeKind::Created,
+        EventKind::Remove(_) => ChangeKind::Removed,
+        _ => ChangeKind::Modified,
+    }
+}
+
+fn within_depth(root: &Path, path: &Path, max_depth: usize) -> bool {
+    path.strip_prefix(root)
+        .map(|rel| rel.components().count() <= max_depth)
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+    use notify::event::CreateKind;
+
+    #[test]
+    fn test_within_depth() {
+        let root = Path::new("/watched");
+        assert!(within_depth(root, Path::new("/watched/a.txt"), 3));
+        assert!(within_depth(root, Path::new("/watched/a/b/c"), 3));
+        assert!(!within_depth(root, Path::new("/watched/a/b/c/d"), 3));
+        assert!(!within_depth(root, Path::new("/elsewhere/a.txt"), 3));
+    }
+
+    #[test]
+    fn test_classify_maps_event_kinds() {
+        assert!(matches!(
+            classify(&EventKind::Create(CreateKind::File)),
+            ChangeKind::Created
+        ));
+        assert!(matches!(
+            classify(&EventKind::Remove(notify::event::RemoveKind::File)),
+            ChangeKind::Removed
+        ));
+        assert!(matches!(
+            classify(&EventKind::Modify(ModifyKind::Data(
+                notify::event::DataChange::Content
+            ))),
+            ChangeKind::Modified
+        ));
+    }
+
+    #[test]
+    fn test_rename_tracker_pairs_by_cookie() {
+        let mut tracker = RenameTracker::new();
+        let now = Instant::now();
+        let from = PathBuf::from("/watched/old.txt");
+
+        tracker.record_from(from.clone(), Some(7), now);
+
+        assert_eq!(tracker.take_pair(Some(7)), Some(from));
+        assert_eq!(tracker.take_pair(Some(7)), None);
+    }
+
+    #[test]
+    fn test_rename_tracker_falls_back_to_fifo_without_cookie() {
+        let mut tracker = RenameTracker::new();
+        let now = Instant::now();
+        let first = PathBuf::from("/watched/first.txt");
+        let second = PathBuf::from("/watched/second.txt");
+
+        tracker.record_from(first.clone(), None, now);
+        tracker.record_from(second.clone(), None, now);
+
+        assert_eq!(tracker.take_pair(None), Some(first));
+        assert_eq!(tracker.take_pair(None), Some(second));
+    }
+
+    #[test]
+    fn test_rename_tracker_flushes_stale_from_events() {
+        let mut tracker = RenameTracker::new();
+        let now = Instant::now();
+        let from = PathBuf::from("/watched/abandoned.txt");
+
+        tracker.record_from(from.clone(), Some(1), now);
+
+        let later = now + Duration::from_millis(MAX_PAIR_AGE_MS + 1);
+        assert_eq!(tracker.take_stale(later), vec![from]);
+        // Already drained, so the same path isn't flushed twice.
+        assert!(tracker.take_stale(later).is_empty());
+    }
+
+    #[test]
+    fn test_ignore_stack_respects_nested_gitignore() {
+        let dir = TempDir::new("ignore_stack");
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        std::fs::write(dir.path().join("sub").join(".gitignore"), b"*.log\n").unwrap();
+        std::fs::write(dir.path().join("sub").join("keep.txt"), b"kept").unwrap();
+        std::fs::write(dir.path().join("sub").join("skip.log"), b"skipped").unwrap();
+
+        let mut stack = IgnoreStack::new();
+        let root = dir.path();
+
+        assert!(stack.is_ignored(root, &root.join("sub").join("skip.log"), false));
+        assert!(!stack.is_ignored(root, &root.join("sub").join("keep.txt"), false));
+    }
+
+    #[test]
+    fn test_queue_event_pairs_split_rename() {
+        let mut pending = HashMap::new();
+        let mut renames = RenameTracker::new();
+        let root = Path::new("/watched");
+        let from = PathBuf::from("/watched/old.txt");
+        let to = PathBuf::from("/watched/new.txt");
+
+        let from_event = Event {
+            kind: EventKind::Modify(ModifyKind::Name(RenameMode::From)),
+            paths: vec![from.clone()],
+            attrs: Default::default(),
+        };
+        queue_event(&from_event, root, 3, &mut pending, &mut renames);
+        assert!(pending.is_empty());
+
+        let to_event = Event {
+            kind: EventKind::Modify(ModifyKind::Name(RenameMode::To)),
+            paths: vec![to.clone()],
+            attrs: Default::default(),
+        };
+        queue_event(&to_event, root, 3, &mut pending, &mut renames);
+
+        assert!(matches!(pending.get(&from).unwrap().kind, ChangeKind::Removed));
+        assert!(matches!(pending.get(&to).unwrap().kind, ChangeKind::Renamed));
+    }
+}
+
+fn build_change_event(
+    path: &Path,
+    kind: ChangeKind,
+    root: &Path,
+    ignore_stack: &mut IgnoreStack,
+    opts: &IndexOptions,
+) -> Option<FileChangeEvent> {
+    if matches!(kind, ChangeKind::Removed) {
+        // The path is gone; synthesize a minimal node from what we still know about it.
+        return Some(FileChangeEvent {
+            node: FileNode {
+                name: path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+                path: path.to_string_lossy().into_owned(),
+                is_dir: false,
+                size: 0,
+                modified: None,
+                file_type: file_type_of(path),
+            },
+            kind,
+        });
+    }
+
+    let metadata = std::fs::metadata(path).ok()?;
+
+    if opts.respect_gitignore && ignore_stack.is_ignored(root, path, metadata.is_dir()) {
+        return None;
+    }
+
+    if !passes_basic_filters(path, &metadata, opts) {
+        return None;
+    }
+
+    Some(FileChangeEvent {
+        node: to_file_node(path, &metadata),
+        kind,
+    })
+}
@@ -2,8 +2,14 @@ use napi_derive::napi;
 use serde::{Deserialize, Serialize};
 
 mod file_indexer;
+mod file_tree;
+mod file_watcher;
+#[cfg(test)]
+mod test_support;
 
 pub use file_indexer::*;
+pub use file_tree::*;
+pub use file_watcher::*;
 
 /// File node representation
 #[derive(Serialize, Deserialize, Debug)]
@@ -27,6 +33,13 @@ pub struct IndexOptions {
     pub max_file_size: Option<u64>,
     pub follow_links: bool,
     pub respect_gitignore: bool,
+    /// Type names to allow, matched against `ignore`'s default type definitions
+    /// (e.g. "rust", "markdown") plus any `custom_types`. Only applied to files.
+    pub types: Option<Vec<String>>,
+    /// Type names to exclude; checked after `types`
+    pub types_not: Option<Vec<String>>,
+    /// Extra `(name, globs)` definitions registered alongside the built-in ones
+    pub custom_types: Option<Vec<TypeDefinition>>,
 }
 
 impl Default for IndexOptions {
@@ -36,10 +49,21 @@ impl Default for IndexOptions {
             max_file_size: Some(10 * 1024 * 1024), // 10MB
             follow_links: false,
             respect_gitignore: true,
+            types: None,
+            types_not: None,
+            custom_types: None,
         }
     }
 }
 
+/// A caller-supplied file-type definition, mirroring `ignore`'s `TypesBuilder::add`
+#[derive(Serialize, Deserialize, Debug)]
+#[napi(object)]
+pub struct TypeDefinition {
+    pub name: String,
+    pub globs: Vec<String>,
+}
+
 /// Result of directory indexing
 #[derive(Serialize, Deserialize, Debug)]
 #[napi(object)]
@@ -48,4 +72,50 @@ pub struct IndexResult {
     pub total_count: usize,
     pub total_size: u64,
     pub duration_ms: u64,
+    /// Failures encountered indexing, whether a whole root passed to
+    /// `index_directories` that doesn't exist/isn't a directory, or a single
+    /// entry hit during a walk (permission denied, metadata read failures,
+    /// etc.) — rather than either being silently dropped.
+    pub errors: Vec<IndexError>,
+}
+
+/// Coarse classification of an indexing failure: per-entry walk failures are
+/// mapped from `std::io::ErrorKind`, failed roots from their existence check
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[napi(string_enum)]
+pub enum ErrorKind {
+    NotFound,
+    PermissionDenied,
+    Unknown,
+}
+
+/// A single root or entry that couldn't be indexed
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[napi(object)]
+pub struct IndexError {
+    pub path: String,
+    pub kind: ErrorKind,
+    pub message: String,
+}
+
+/// A directory (or file) node in the tree returned by `index_tree`.
+/// `aggregate_size` sums every descendant file's bytes (directories themselves
+/// contribute no bytes); `aggregate_count` counts this node plus every
+/// descendant entry, directories included.
+#[derive(Serialize, Deserialize, Debug)]
+#[napi(object)]
+pub struct DirNode {
+    pub node: FileNode,
+    pub children: Vec<DirNode>,
+    pub aggregate_size: u64,
+    pub aggregate_count: u64,
+}
+
+/// Result of `index_tree`: the aggregated tree plus any per-entry failures
+/// encountered while building it (same accounting as `IndexResult.errors`)
+#[derive(Serialize, Deserialize, Debug)]
+#[napi(object)]
+pub struct TreeResult {
+    pub root: DirNode,
+    pub errors: Vec<IndexError>,
 }
@@ -0,0 +1,33 @@
+//! Shared test fixtures used by `file_indexer`, `file_tree`, and `file_watcher`'s
+//! `#[cfg(test)] mod tests`.
+
+use std::path::{Path, PathBuf};
+
+/// A scratch directory under the OS temp dir, unique per test, removed on drop
+pub(crate) struct TempDir(PathBuf);
+
+impl TempDir {
+    pub(crate) fn new(label: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!(
+            "desktop_mate_test_{}_{}_{}",
+            label,
+            std::process::id(),
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}